@@ -0,0 +1,3 @@
+pub mod read_proto;
+pub mod relu_op;
+pub mod tensor;
@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+use crate::read_proto::proto_structure::{KindOf, Proto, ProtoAnnotation};
+
+/*
+A map<K, V> field's key can only ever decode to a string or an integer scalar (protobuf forbids
+float/bytes/message map keys), so this small Eq+Hash wrapper is enough to let DynValue::Map use a
+real HashMap instead of falling back to a linear Vec of pairs.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+  Str(String),
+  Int(i64)
+}
+impl MapKey {
+  fn from_value(value: &DynValue) -> Self {
+    match value {
+      DynValue::Str(s) => MapKey::Str(s.clone()),
+      DynValue::Bool(b) => MapKey::Int(*b as i64),
+      _ => MapKey::Int(match value { DynValue::Varint(n) => *n, _ => 0 })
+    }
+  }
+}
+
+/*
+This enum represents a single decoded value inside a DynMessage. Since the decoder has no
+compile-time knowledge of the ONNX message types (it only knows what the parsed .proto schema
+tells it at runtime), every field is stored behind this dynamic representation instead of a
+hand-written struct.
+  - Varint: a decoded int32/int64/uint32/uint64/sint32/sint64 value (wire type 0), and also any
+            fixed64/sfixed64 (wire type 1) or fixed32/sfixed32 (wire type 5) integer value
+  - Bool: a varint that the schema declared as "bool"
+  - Fixed64: a decoded double value (wire type 1)
+  - Fixed32: a decoded float value (wire type 5)
+  - Str: a LEN-delimited field the schema declared as "string"
+  - Bytes: a LEN-delimited field the schema declared as "bytes" (kept raw, never UTF-8 decoded)
+  - Enum: a varint decoded against an `enum` Proto. Per proto3 open-enum semantics the raw number is
+          always kept even when it isn't one of the declared values, see ProtoAttribute/Proto::from_i32
+  - Message: a LEN-delimited field that nests another DynMessage
+  - Map: the merged occurrences of a `map<K, V>` field, keyed by its decoded entries (see ProtoAttribute::map_type)
+  - Repeated: the accumulated values of a field that appeared more than once (see ProtoAnnotation::Repeated)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynValue {
+  Varint(i64),
+  Bool(bool),
+  Fixed64(f64),
+  Fixed32(f32),
+  Str(String),
+  Bytes(Vec<u8>),
+  Enum(i32),
+  Message(DynMessage),
+  Map(HashMap<MapKey, DynValue>),
+  Repeated(Vec<DynValue>)
+}
+
+//the proto3 default value for a scalar type, used when a map entry's key or value was left out of
+//its LEN payload (protobuf allows either half of a map entry message to be absent)
+fn zero_value_for(attribute_type: &str) -> DynValue {
+  match attribute_type {
+    "string" => DynValue::Str(String::new()),
+    "bytes" => DynValue::Bytes(Vec::new()),
+    "bool" => DynValue::Bool(false),
+    "float" => DynValue::Fixed32(0.0),
+    "double" => DynValue::Fixed64(0.0),
+    _ => DynValue::Varint(0)
+  }
+}
+
+/*
+This structure is the dynamic counterpart of a .proto "message": instead of a hand-written Rust
+struct it holds a HashMap<attribute_name, DynValue>, populated field-by-field while walking the
+wire format described by a Proto/ProtoAttribute schema. Operator code can then read fields by name
+without the decoder needing to know the ONNX message types ahead of time.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DynMessage {
+  pub fields: HashMap<String, DynValue>
+}
+impl DynMessage {
+  pub(crate) fn new() -> Self {
+    Self { fields: HashMap::new() }
+  }
+
+  //inserts a newly decoded value under `name`, folding it into a Repeated(..) if the field
+  //already appeared (this is what lets the decoder merge packed and non-packed occurrences, see decode_len_field)
+  fn insert(&mut self, name: String, value: DynValue) {
+    match self.fields.remove(&name) {
+      None => { self.fields.insert(name, value); }
+      Some(DynValue::Repeated(mut values)) => {
+        values.push(value);
+        self.fields.insert(name, DynValue::Repeated(values));
+      }
+      Some(previous) => {
+        self.fields.insert(name, DynValue::Repeated(vec![previous, value]));
+      }
+    }
+  }
+
+  //inserts one decoded `map<K, V>` entry under `name`, creating the backing HashMap on first use.
+  //proto3 gives "last entry wins" semantics for a repeated key, which a plain HashMap insert already does
+  fn insert_map_entry(&mut self, name: String, key: MapKey, value: DynValue) {
+    match self.fields.entry(name).or_insert_with(|| DynValue::Map(HashMap::new())) {
+      DynValue::Map(entries) => { entries.insert(key, value); }
+      _ => unreachable!("a map field is always stored as DynValue::Map")
+    }
+  }
+
+  //reads an enum field, falling back to the proto3 zero-numbered default when the field was absent
+  //from the wire entirely (as opposed to present with an unrecognized number, which decode() already
+  //preserves via DynValue::Enum)
+  pub fn enum_field(&self, name: &str) -> i32 {
+    match self.fields.get(name) {
+      Some(DynValue::Enum(n)) => *n,
+      _ => 0
+    }
+  }
+}
+
+//decodes a base-128 varint (little-endian, continuation bit in the MSB of each byte) starting at
+//`offset`, returning the decoded value and the offset of the first byte after it
+fn decode_varint(bytes: &[u8], offset: usize) -> (u64, usize) {
+  let mut result: u64 = 0;
+  let mut shift = 0;
+  let mut pos = offset;
+  loop {
+    let byte = bytes[pos];
+    result |= ((byte & 0x7F) as u64) << shift;
+    pos += 1;
+    if byte & 0x80 == 0 {
+      break;
+    }
+    shift += 7;
+  }
+  (result, pos)
+}
+
+//undoes zigzag encoding, used for sint32/sint64: (n >> 1) ^ -(n & 1)
+fn zigzag_decode(n: u64) -> i64 {
+  ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
+
+//splits a decoded field key varint into its (tag, wire_type) pair: tag = key >> 3, wire_type = key & 0x7
+fn split_key(key: u64) -> (i32, u8) {
+  ((key >> 3) as i32, (key & 0x7) as u8)
+}
+
+/*
+Walks `bytes` as the binary encoding of `root`, resolving every field tag against
+`root.attributes` to learn its declared type and dispatching on the wire type carried by the key
+varint. Unknown tags are skipped by consuming exactly the number of bytes their wire type implies,
+so forward-compatible ONNX files (carrying fields newer than this copy of the schema) still decode.
+
+A repeated scalar field (e.g. `TensorProto.dims`) may legally show up on the wire either "packed"
+(one LEN field whose payload is a back-to-back run of scalars, see decode_len_field) or "unpacked"
+(the same tag repeated several times, each carrying a single scalar), since proto2 producers may
+emit either form. Both are merged into the same Vec via DynMessage::insert, so callers never need
+to care which form a given .onnx file used.
+ */
+pub fn decode(bytes: &[u8], root: &Proto, proto_map: &HashMap<String, Proto>) -> DynMessage {
+  let mut message = DynMessage::new();
+  let mut offset = 0;
+  while offset < bytes.len() {
+    let (key, next) = decode_varint(bytes, offset);
+    offset = next;
+    let (tag, wire_type) = split_key(key);
+
+    match root.attributes.get(&tag) {
+      Some(attribute) => {
+        let (value, next) = decode_field(bytes, offset, wire_type, &attribute.attribute_type, root, proto_map);
+        offset = next;
+        if let Some(value) = value {
+          match (&attribute.map_type, &value) {
+            //each occurrence of a map field is an entry message carrying an optional "key"/"value" pair
+            (Some(map_type), DynValue::Message(entry)) => {
+              let key = entry.fields.get("key").cloned().unwrap_or_else(|| zero_value_for(&map_type.key_type));
+              let val = entry.fields.get("value").cloned().unwrap_or_else(|| zero_value_for(&map_type.value_type));
+              message.insert_map_entry(attribute.attribute_name.clone(), MapKey::from_value(&key), val);
+            }
+            //a packed-repeated scalar unpacks into several values in one LEN field; merge them all in
+            (None, DynValue::Repeated(values)) if attribute.annotation == ProtoAnnotation::Repeated => {
+              for v in values.clone() {
+                message.insert(attribute.attribute_name.clone(), v);
+              }
+            }
+            _ => message.insert(attribute.attribute_name.clone(), value)
+          }
+        }
+      }
+      None => offset = skip_unknown_field(bytes, offset, wire_type)
+    }
+  }
+  message
+}
+
+//decodes the payload of a single field once its declared type is known, returning the value (None
+//only for a wire type other than 0, 1, 2 or 5; a zero-length packed payload yields an empty
+//Repeated, which decode() then inserts nothing for) and the offset just past it
+fn decode_field(
+  bytes: &[u8],
+  offset: usize,
+  wire_type: u8,
+  attribute_type: &str,
+  root: &Proto,
+  proto_map: &HashMap<String, Proto>
+) -> (Option<DynValue>, usize) {
+  match wire_type {
+    0 => {
+      let (raw, next) = decode_varint(bytes, offset);
+      let is_enum = root.contents.get(attribute_type).or_else(|| proto_map.get(attribute_type))
+        .is_some_and(|nested| nested.kind_of == KindOf::Enum);
+      let value = match attribute_type {
+        _ if is_enum => DynValue::Enum(raw as i32),
+        "sint32" | "sint64" => DynValue::Varint(zigzag_decode(raw)),
+        "bool" => DynValue::Bool(raw != 0),
+        _ => DynValue::Varint(raw as i64)
+      };
+      (Some(value), next)
+    }
+    1 => {
+      let value = match attribute_type {
+        "double" => DynValue::Fixed64(f64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())),
+        //fixed64 is unsigned on the wire, unlike sfixed64; decoding it via u64 first keeps that
+        //distinction explicit even though both land in the same i64-backed Varint (see zero_value_for)
+        "fixed64" => DynValue::Varint(u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap()) as i64),
+        _ => DynValue::Varint(i64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap()))
+      };
+      (Some(value), offset + 8)
+    }
+    5 => {
+      let value = match attribute_type {
+        "float" => DynValue::Fixed32(f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())),
+        //fixed32 is unsigned on the wire: zero-extend through u32 rather than sign-extending through
+        //i32, or a value with its top bit set would decode as negative instead of as the correct
+        //large positive number
+        "fixed32" => DynValue::Varint(u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as i64),
+        _ => DynValue::Varint(i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as i64)
+      };
+      (Some(value), offset + 4)
+    }
+    2 => decode_len_field(bytes, offset, attribute_type, root, proto_map),
+    _ => (None, offset)
+  }
+}
+
+//decodes a LEN-delimited field: a length-prefixed nested message, string, byte blob, or (when the
+//declared type is a scalar) a packed-repeated run of that scalar
+fn decode_len_field(
+  bytes: &[u8],
+  offset: usize,
+  attribute_type: &str,
+  root: &Proto,
+  proto_map: &HashMap<String, Proto>
+) -> (Option<DynValue>, usize) {
+  let (len, start) = decode_varint(bytes, offset);
+  let end = start + len as usize;
+  let payload = &bytes[start..end];
+
+  let value = if let Some(nested) = root.contents.get(attribute_type).or_else(|| proto_map.get(attribute_type)) {
+    DynValue::Message(decode(payload, nested, proto_map))
+  } else {
+    match attribute_type {
+      "string" => DynValue::Str(String::from_utf8_lossy(payload).into_owned()),
+      "bytes" => DynValue::Bytes(payload.to_vec()),
+      //any other scalar type arriving as LEN is a packed-repeated run: unpack it into a Vec
+      scalar => DynValue::Repeated(decode_packed_scalars(payload, scalar))
+    }
+  };
+  (Some(value), end)
+}
+
+//unpacks a payload made of back-to-back scalars of the same declared type with no per-element tag
+fn decode_packed_scalars(payload: &[u8], attribute_type: &str) -> Vec<DynValue> {
+  let mut values = Vec::new();
+  let mut offset = 0;
+  while offset < payload.len() {
+    let (value, next) = match attribute_type {
+      "float" => (DynValue::Fixed32(f32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap())), offset + 4),
+      "double" => (DynValue::Fixed64(f64::from_le_bytes(payload[offset..offset + 8].try_into().unwrap())), offset + 8),
+      //fixed32/fixed64 are unsigned, sfixed32/sfixed64 are signed: zero-extend the former through
+      //u32/u64 and sign-extend the latter through i32/i64, see decode_field's wire types 1 and 5
+      "fixed32" => (DynValue::Varint(u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap()) as i64), offset + 4),
+      "sfixed32" => (DynValue::Varint(i32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap()) as i64), offset + 4),
+      "fixed64" => (DynValue::Varint(u64::from_le_bytes(payload[offset..offset + 8].try_into().unwrap()) as i64), offset + 8),
+      "sfixed64" => (DynValue::Varint(i64::from_le_bytes(payload[offset..offset + 8].try_into().unwrap())), offset + 8),
+      "sint32" | "sint64" => {
+        let (raw, next) = decode_varint(payload, offset);
+        (DynValue::Varint(zigzag_decode(raw)), next)
+      }
+      "bool" => {
+        let (raw, next) = decode_varint(payload, offset);
+        (DynValue::Bool(raw != 0), next)
+      }
+      _ => {
+        let (raw, next) = decode_varint(payload, offset);
+        (DynValue::Varint(raw as i64), next)
+      }
+    };
+    values.push(value);
+    offset = next;
+  }
+  values
+}
+
+//skips a field whose tag is not present in the schema, consuming exactly the bytes its wire type implies
+fn skip_unknown_field(bytes: &[u8], offset: usize, wire_type: u8) -> usize {
+  match wire_type {
+    0 => decode_varint(bytes, offset).1,
+    1 => offset + 8,
+    2 => {
+      let (len, start) = decode_varint(bytes, offset);
+      start + len as usize
+    }
+    5 => offset + 4,
+    _ => offset
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::read_proto::proto_structure::ProtoAttribute;
+
+  fn key(tag: i32, wire_type: u8) -> u8 {
+    ((tag as u8) << 3) | wire_type
+  }
+
+  #[test]
+  fn varint_decodes_single_and_multi_byte_values() {
+    assert_eq!(decode_varint(&[0x05], 0), (5, 1));
+    assert_eq!(decode_varint(&[0xAC, 0x02], 0), (300, 2));
+  }
+
+  #[test]
+  fn zigzag_decode_matches_proto_spec() {
+    assert_eq!(zigzag_decode(0), 0);
+    assert_eq!(zigzag_decode(1), -1);
+    assert_eq!(zigzag_decode(2), 1);
+    assert_eq!(zigzag_decode(3), -2);
+  }
+
+  #[test]
+  fn decode_round_trips_every_scalar_wire_type_plus_a_nested_message() {
+    let mut nested = Proto::new(KindOf::Message);
+    nested.attributes.insert(1, ProtoAttribute { attribute_name: "inner".to_string(), attribute_type: "int32".to_string(), ..ProtoAttribute::new() });
+
+    let mut root = Proto::new(KindOf::Message);
+    root.attributes.insert(1, ProtoAttribute { attribute_name: "name".to_string(), attribute_type: "string".to_string(), ..ProtoAttribute::new() });
+    root.attributes.insert(2, ProtoAttribute { attribute_name: "flag".to_string(), attribute_type: "double".to_string(), ..ProtoAttribute::new() });
+    root.attributes.insert(3, ProtoAttribute { attribute_name: "ratio".to_string(), attribute_type: "float".to_string(), ..ProtoAttribute::new() });
+    root.attributes.insert(4, ProtoAttribute { attribute_name: "blob".to_string(), attribute_type: "bytes".to_string(), ..ProtoAttribute::new() });
+    root.attributes.insert(5, ProtoAttribute { attribute_name: "child".to_string(), attribute_type: "Nested".to_string(), ..ProtoAttribute::new() });
+    root.contents.insert("Nested".to_string(), nested);
+
+    let mut bytes = Vec::new();
+    bytes.push(key(1, 2)); bytes.push(2); bytes.extend(b"hi");
+    bytes.push(key(2, 1)); bytes.extend(3.5f64.to_le_bytes());
+    bytes.push(key(3, 5)); bytes.extend(1.5f32.to_le_bytes());
+    bytes.push(key(4, 2)); bytes.push(2); bytes.extend([0xDE, 0xAD]);
+    let inner = vec![key(1, 0), 7];
+    bytes.push(key(5, 2)); bytes.push(inner.len() as u8); bytes.extend(inner);
+
+    let message = decode(&bytes, &root, &HashMap::new());
+
+    assert_eq!(message.fields.get("name"), Some(&DynValue::Str("hi".to_string())));
+    assert_eq!(message.fields.get("flag"), Some(&DynValue::Fixed64(3.5)));
+    assert_eq!(message.fields.get("ratio"), Some(&DynValue::Fixed32(1.5)));
+    assert_eq!(message.fields.get("blob"), Some(&DynValue::Bytes(vec![0xDE, 0xAD])));
+    match message.fields.get("child") {
+      Some(DynValue::Message(inner)) => assert_eq!(inner.fields.get("inner"), Some(&DynValue::Varint(7))),
+      other => panic!("expected a nested message, got {:?}", other)
+    }
+  }
+
+  #[test]
+  fn decode_merges_packed_and_unpacked_occurrences_of_a_repeated_scalar() {
+    let mut root = Proto::new(KindOf::Message);
+    root.attributes.insert(1, ProtoAttribute { annotation: ProtoAnnotation::Repeated, attribute_name: "dims".to_string(), attribute_type: "int64".to_string(), ..ProtoAttribute::new() });
+
+    let mut bytes = Vec::new();
+    bytes.push(key(1, 2)); bytes.push(2); bytes.extend([1, 2]); //packed: dims += [1, 2]
+    bytes.push(key(1, 0)); bytes.push(3); //unpacked: dims += 3
+
+    let message = decode(&bytes, &root, &HashMap::new());
+
+    assert_eq!(
+      message.fields.get("dims"),
+      Some(&DynValue::Repeated(vec![DynValue::Varint(1), DynValue::Varint(2), DynValue::Varint(3)]))
+    );
+  }
+
+  #[test]
+  fn enum_fields_keep_open_enum_semantics() {
+    let mut color = Proto::new(KindOf::Enum);
+    color.enum_values.insert(0, "RED".to_string());
+    color.enum_names.insert("RED".to_string(), 0);
+    color.enum_values.insert(2, "BLUE".to_string());
+    color.enum_names.insert("BLUE".to_string(), 2);
+
+    let mut root = Proto::new(KindOf::Message);
+    root.attributes.insert(1, ProtoAttribute { attribute_name: "kind".to_string(), attribute_type: "Color".to_string(), ..ProtoAttribute::new() });
+    root.contents.insert("Color".to_string(), color.clone());
+
+    //a known value still decodes to Enum, never unwrapped to a plain Varint
+    let known = decode(&[key(1, 0), 2], &root, &HashMap::new());
+    assert_eq!(known.fields.get("kind"), Some(&DynValue::Enum(2)));
+
+    //an unrecognized number is not an error: proto3 open enums keep the raw number around
+    let unknown = decode(&[key(1, 0), 99], &root, &HashMap::new());
+    assert_eq!(unknown.fields.get("kind"), Some(&DynValue::Enum(99)));
+    assert!(!color.is_valid(99));
+    assert_eq!(color.from_i32(2), Some("BLUE"));
+
+    //a field absent from the wire entirely defaults to the zero-numbered value
+    assert_eq!(DynMessage::new().enum_field("kind"), 0);
+  }
+
+  #[test]
+  fn decode_merges_repeated_map_entries_into_a_single_map() {
+    let mut entry = Proto::new(KindOf::Message);
+    entry.attributes.insert(1, ProtoAttribute { attribute_name: "key".to_string(), attribute_type: "string".to_string(), ..ProtoAttribute::new() });
+    entry.attributes.insert(2, ProtoAttribute { attribute_name: "value".to_string(), attribute_type: "int64".to_string(), ..ProtoAttribute::new() });
+
+    let mut root = Proto::new(KindOf::Message);
+    root.attributes.insert(1, ProtoAttribute {
+      annotation: ProtoAnnotation::Map,
+      attribute_name: "labels".to_string(),
+      attribute_type: "LabelsEntry".to_string(),
+      map_type: Some(crate::read_proto::proto_structure::MapType { key_type: "string".to_string(), value_type: "int64".to_string() })
+    });
+    root.contents.insert("LabelsEntry".to_string(), entry);
+
+    let entry_bytes = |name: &str, value: i64| -> Vec<u8> {
+      let mut bytes = vec![key(1, 2), name.len() as u8];
+      bytes.extend(name.as_bytes());
+      bytes.push(key(2, 0));
+      bytes.push(value as u8);
+      bytes
+    };
+    let mut bytes = Vec::new();
+    for (name, value) in [("a", 1), ("b", 2)] {
+      let entry_bytes = entry_bytes(name, value);
+      bytes.push(key(1, 2));
+      bytes.push(entry_bytes.len() as u8);
+      bytes.extend(entry_bytes);
+    }
+
+    let message = decode(&bytes, &root, &HashMap::new());
+
+    let mut expected = HashMap::new();
+    expected.insert(MapKey::Str("a".to_string()), DynValue::Varint(1));
+    expected.insert(MapKey::Str("b".to_string()), DynValue::Varint(2));
+    assert_eq!(message.fields.get("labels"), Some(&DynValue::Map(expected)));
+  }
+
+  #[test]
+  fn fixed32_decodes_as_unsigned_while_sfixed32_stays_signed() {
+    let mut root = Proto::new(KindOf::Message);
+    root.attributes.insert(1, ProtoAttribute { attribute_name: "unsigned".to_string(), attribute_type: "fixed32".to_string(), ..ProtoAttribute::new() });
+    root.attributes.insert(2, ProtoAttribute { attribute_name: "signed".to_string(), attribute_type: "sfixed32".to_string(), ..ProtoAttribute::new() });
+
+    let mut bytes = Vec::new();
+    bytes.push(key(1, 5)); bytes.extend(0xFFFFFFFFu32.to_le_bytes()); //top bit set
+    bytes.push(key(2, 5)); bytes.extend((-1i32).to_le_bytes());
+
+    let message = decode(&bytes, &root, &HashMap::new());
+
+    assert_eq!(message.fields.get("unsigned"), Some(&DynValue::Varint(0xFFFFFFFFu32 as i64)));
+    assert_eq!(message.fields.get("signed"), Some(&DynValue::Varint(-1)));
+  }
+}
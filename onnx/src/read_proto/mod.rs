@@ -0,0 +1,4 @@
+pub mod proto_structure;
+pub mod proto_parser;
+pub mod proto_loader;
+pub mod decoder;
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::read_proto::proto_parser::parse_proto_file;
+use crate::read_proto::proto_structure::Proto;
+
+/*
+Real ONNX schemas are split across several .proto files tied together with `import "other.proto";`
+and `package onnx;` declarations. This module walks that import graph starting from a single entry
+file and merges every file's top-level message/enum declarations into one proto_map keyed by
+package-qualified name (`package.Message`), rewriting every field's `attribute_type` that names one
+of them along the way so the decoder can follow cross-file references unambiguously. Nested
+declarations are not given keys of their own: they stay in their parent's `contents` under their
+unqualified names.
+ */
+#[derive(Debug)]
+pub enum ProtoLoadError {
+  MissingImport(String),
+  CyclicImport(Vec<String>)
+}
+
+//`name -> fully_qualified_name` for everything visible while parsing a given file: its own
+//top-level declarations plus whatever its (transitive) imports exported
+type Scope = HashMap<String, String>;
+
+/*
+Loads `entry_path` and every file it (transitively) imports, returning a single proto_map keyed by
+fully-qualified name. Detects both a missing import file and an import cycle instead of silently
+producing an empty Proto for either.
+ */
+pub fn load_proto_files(entry_path: &Path) -> Result<HashMap<String, Proto>, ProtoLoadError> {
+  let mut merged = HashMap::new();
+  let mut loaded: HashMap<PathBuf, Scope> = HashMap::new();
+  let mut visiting = Vec::new();
+  load_file(entry_path, &mut visiting, &mut loaded, &mut merged)?;
+  Ok(merged)
+}
+
+fn load_file(
+  path: &Path,
+  visiting: &mut Vec<PathBuf>,
+  loaded: &mut HashMap<PathBuf, Scope>,
+  merged: &mut HashMap<String, Proto>
+) -> Result<Scope, ProtoLoadError> {
+  let path = path.to_path_buf();
+  if let Some(scope) = loaded.get(&path) {
+    return Ok(scope.clone());
+  }
+  if visiting.contains(&path) {
+    let mut cycle: Vec<String> = visiting.iter().map(|p| p.display().to_string()).collect();
+    cycle.push(path.display().to_string());
+    return Err(ProtoLoadError::CyclicImport(cycle));
+  }
+  visiting.push(path.clone());
+
+  let source = std::fs::read_to_string(&path)
+    .map_err(|_| ProtoLoadError::MissingImport(path.display().to_string()))?;
+  let parsed = parse_proto_file(&source);
+  let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+  //imports are resolved first so their exported names are in scope while qualifying this file's own types
+  let mut scope: Scope = HashMap::new();
+  for import in &parsed.imports {
+    let imported_scope = load_file(&dir.join(import), visiting, loaded, merged)?;
+    scope.extend(imported_scope);
+  }
+
+  //this file's own declarations take precedence over same-named imports (they shadow them, same as
+  //a local variable shadowing an outer one)
+  for name in parsed.proto_map.keys() {
+    let qualified = match &parsed.package {
+      Some(package) => format!("{}.{}", package, name),
+      None => name.clone()
+    };
+    scope.insert(name.clone(), qualified);
+  }
+
+  let mut local_map = parsed.proto_map;
+  for proto in local_map.values_mut() {
+    qualify_types(proto, &scope);
+  }
+  for (name, proto) in local_map {
+    merged.insert(scope[&name].clone(), proto);
+  }
+
+  visiting.pop();
+  loaded.insert(path, scope.clone());
+  Ok(scope)
+}
+
+//rewrites every attribute_type that names a message/enum in `scope` to its canonical fully-qualified
+//key, recursing into nested oneof/message/enum contents; scalar types (int32, string, ...) never
+//appear in scope so they pass through untouched
+fn qualify_types(proto: &mut Proto, scope: &Scope) {
+  for attribute in proto.attributes.values_mut() {
+    if let Some(qualified) = scope.get(&attribute.attribute_type) {
+      attribute.attribute_type = qualified.clone();
+    }
+  }
+  for nested in proto.contents.values_mut() {
+    qualify_types(nested, scope);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  #[test]
+  fn load_proto_files_resolves_a_cross_file_import_into_one_qualified_proto_map() {
+    let dir = std::env::temp_dir().join("onnx_proto_loader_test_cross_file_import");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("common.proto"), "package onnx; message Dim { optional int64 value = 1; }").unwrap();
+    fs::write(dir.join("main.proto"), "package onnx; import \"common.proto\"; message Shape { repeated Dim dims = 1; }").unwrap();
+
+    let proto_map = load_proto_files(&dir.join("main.proto")).unwrap();
+
+    assert!(proto_map.contains_key("onnx.Dim"));
+    let shape = proto_map.get("onnx.Shape").expect("Shape should have been loaded from main.proto");
+    //the imported "Dim" type must be rewritten to its fully-qualified name so decode() can find it
+    //in proto_map regardless of which file originally declared it
+    assert_eq!(shape.attributes[&1].attribute_type, "onnx.Dim");
+
+    fs::remove_dir_all(&dir).ok();
+  }
+
+  #[test]
+  fn load_proto_files_reports_a_missing_import_instead_of_an_empty_proto() {
+    let dir = std::env::temp_dir().join("onnx_proto_loader_test_missing_import");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.proto"), "import \"absent.proto\"; message Shape {}").unwrap();
+
+    let result = load_proto_files(&dir.join("main.proto"));
+
+    assert!(matches!(result, Err(ProtoLoadError::MissingImport(_))));
+
+    fs::remove_dir_all(&dir).ok();
+  }
+}
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::read_proto::proto_structure::{KindOf, MapType, Proto, ProtoAnnotation, ProtoAttribute};
+
+/*
+This module turns the text of a .proto file into the Proto/ProtoAttribute tree described in
+proto_structure.rs. It is a small recursive-descent parser: `//` line comments are stripped (block
+comments are not), the remaining text is tokenized on whitespace and on the punctuation that
+matters to the grammar (`{ } ; = < > , [ ]`),
+and the token stream is then walked top to bottom following the nesting of `message`/`oneof`/`enum`
+blocks. Bracketed field options (`[packed = true]`), a standard proto2 idiom the real onnx.proto
+uses on every packed-repeated field, are recognized just enough to be skipped (see skip_field_options).
+ */
+
+//removes `//` line comments so they can never be mistaken for tokens
+fn strip_comments(source: &str) -> String {
+  source
+    .lines()
+    .map(|line| match line.find("//") {
+      Some(idx) => &line[..idx],
+      None => line
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+//splits the source into tokens, surrounding the grammar's punctuation with spaces first so that
+//e.g. `int32 number=5;` and `int32 number = 5 ;` tokenize identically
+fn tokenize(source: &str) -> Vec<String> {
+  let mut spaced = String::with_capacity(source.len());
+  for ch in source.chars() {
+    match ch {
+      '{' | '}' | ';' | '=' | '<' | '>' | ',' | '[' | ']' => {
+        spaced.push(' ');
+        spaced.push(ch);
+        spaced.push(' ');
+      }
+      _ => spaced.push(ch)
+    }
+  }
+  spaced.split_whitespace().map(str::to_string).collect()
+}
+
+//consumes a top-level statement this parser doesn't model yet (e.g. `syntax = "proto3";`) by
+//skipping forward to its terminating semicolon
+fn skip_statement(tokens: &[String], pos: &mut usize) {
+  while *pos < tokens.len() && tokens[*pos] != ";" {
+    *pos += 1;
+  }
+  *pos += 1; //consume the ';'
+}
+
+//skips a bracketed field option list like `[packed = true]` (proto2's standard way to mark a
+//repeated scalar field as packed-encoded); this grammar subset has no nesting, so it's enough to
+//scan forward to the matching ']'
+fn skip_field_options(tokens: &[String], pos: &mut usize) {
+  if tokens[*pos] == "[" {
+    while tokens[*pos] != "]" {
+      *pos += 1;
+    }
+    *pos += 1; //consume ']'
+  }
+}
+
+//parses `optional string name = 1;` (or the repeated/required variants, or a bare `string name = 1;`
+//which proto3 treats as implicitly optional) into its tag and ProtoAttribute
+fn parse_field(tokens: &[String], pos: &mut usize) -> (i32, ProtoAttribute) {
+  let annotation = match ProtoAnnotation::from_str(&tokens[*pos]) {
+    Ok(annotation) => {
+      *pos += 1;
+      annotation
+    }
+    Err(_) => ProtoAnnotation::default()
+  };
+  let attribute_type = tokens[*pos].clone();
+  *pos += 1;
+  let attribute_name = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume '='
+  let tag: i32 = tokens[*pos].parse().expect("field tag must be an integer");
+  *pos += 1;
+  skip_field_options(tokens, pos);
+  *pos += 1; //consume ';'
+
+  (tag, ProtoAttribute { annotation, attribute_name, attribute_type, ..ProtoAttribute::new() })
+}
+
+//capitalizes the first character of a field name, used to mirror protoc's convention of naming
+//the synthesized entry message of `map<K, V> foo = N;` as `FooEntry`
+fn capitalize(name: &str) -> String {
+  let mut chars = name.chars();
+  match chars.next() {
+    None => String::new(),
+    Some(first) => first.to_uppercase().collect::<String>() + chars.as_str()
+  }
+}
+
+//parses `map<K, V> name = N;` into the (tag, ProtoAttribute) pair of the field itself plus the
+//synthesized `NameEntry` message (key at tag 1, value at tag 2) that the owning Proto must store in
+//its `contents` so the decoder can recurse into it like any other nested message
+fn parse_map_field(tokens: &[String], pos: &mut usize) -> (i32, ProtoAttribute, String, Proto) {
+  *pos += 1; //consume 'map'
+  *pos += 1; //consume '<'
+  let key_type = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume ','
+  let value_type = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume '>'
+  let attribute_name = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume '='
+  let tag: i32 = tokens[*pos].parse().expect("field tag must be an integer");
+  *pos += 1;
+  skip_field_options(tokens, pos);
+  *pos += 1; //consume ';'
+
+  let entry_name = format!("{}Entry", capitalize(&attribute_name));
+  let mut entry = Proto::new(KindOf::Message);
+  entry.attributes.insert(1, ProtoAttribute { annotation: ProtoAnnotation::Optional, attribute_name: "key".to_string(), attribute_type: key_type.clone(), ..ProtoAttribute::new() });
+  entry.attributes.insert(2, ProtoAttribute { annotation: ProtoAnnotation::Optional, attribute_name: "value".to_string(), attribute_type: value_type.clone(), ..ProtoAttribute::new() });
+
+  let attribute = ProtoAttribute {
+    annotation: ProtoAnnotation::Map,
+    attribute_name,
+    attribute_type: entry_name.clone(),
+    map_type: Some(MapType { key_type, value_type })
+  };
+  (tag, attribute, entry_name, entry)
+}
+
+//parses `enum Foo { BAR = 0; BAZ = 2; }` into a Proto carrying the number<->name maps
+fn parse_enum(tokens: &[String], pos: &mut usize) -> (String, Proto) {
+  *pos += 1; //consume 'enum'
+  let name = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume '{'
+
+  let mut proto = Proto::new(KindOf::Enum);
+  while tokens[*pos] != "}" {
+    match tokens[*pos].as_str() {
+      //`option allow_alias = true;` and similar: not modeled, skip to the terminating ';'
+      "option" => skip_statement(tokens, pos),
+      _ => {
+        let value_name = tokens[*pos].clone();
+        *pos += 1;
+        *pos += 1; //consume '='
+        let number: i32 = tokens[*pos].parse().expect("enum value must be an integer");
+        *pos += 1;
+        *pos += 1; //consume ';'
+
+        proto.enum_values.insert(number, value_name.clone());
+        proto.enum_names.insert(value_name, number);
+      }
+    }
+  }
+  *pos += 1; //consume '}'
+
+  (name, proto)
+}
+
+//parses `oneof Address { string city = 3; int32 number = 5; }`
+fn parse_oneof(tokens: &[String], pos: &mut usize) -> (String, Proto) {
+  *pos += 1; //consume 'oneof'
+  let name = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume '{'
+
+  let mut proto = Proto::new(KindOf::OneOf);
+  while tokens[*pos] != "}" {
+    let (tag, attribute) = parse_field(tokens, pos);
+    proto.attributes.insert(tag, attribute);
+  }
+  *pos += 1; //consume '}'
+
+  (name, proto)
+}
+
+//parses `message Person { ... }`, recursing into any nested message/oneof/enum block
+fn parse_message(tokens: &[String], pos: &mut usize) -> (String, Proto) {
+  *pos += 1; //consume 'message'
+  let name = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume '{'
+
+  let mut proto = Proto::new(KindOf::Message);
+  while tokens[*pos] != "}" {
+    match tokens[*pos].as_str() {
+      "message" => {
+        let (nested_name, nested) = parse_message(tokens, pos);
+        proto.contents.insert(nested_name, nested);
+      }
+      "oneof" => {
+        let (nested_name, nested) = parse_oneof(tokens, pos);
+        //a oneof has no tag of its own on the wire: its members are ordinary fields of the
+        //containing message, so they must be reachable from root.attributes for decode() to find
+        //them. The grouping is kept in contents too, for introspection of which fields are mutually exclusive.
+        for (tag, attribute) in nested.attributes.clone() {
+          proto.attributes.insert(tag, attribute);
+        }
+        proto.contents.insert(nested_name, nested);
+      }
+      "enum" => {
+        let (nested_name, nested) = parse_enum(tokens, pos);
+        proto.contents.insert(nested_name, nested);
+      }
+      "map" => {
+        let (tag, attribute, entry_name, entry) = parse_map_field(tokens, pos);
+        proto.contents.insert(entry_name, entry);
+        proto.attributes.insert(tag, attribute);
+      }
+      //`reserved 8, 9, 10;`/`reserved "old_name";` and `option deprecated = true;`: neither is
+      //modeled by this parser, so both are skipped to their terminating ';' the same way
+      //skip_statement already handles top-level statements like `syntax = "proto3";`
+      "reserved" | "option" => skip_statement(tokens, pos),
+      _ => {
+        let (tag, attribute) = parse_field(tokens, pos);
+        proto.attributes.insert(tag, attribute);
+      }
+    }
+  }
+  *pos += 1; //consume '}'
+
+  (name, proto)
+}
+
+//parses `package onnx;` (a dotted package name like `onnx.ml` tokenizes as a single identifier
+//since '.' is not one of the punctuation characters split out by tokenize)
+fn parse_package(tokens: &[String], pos: &mut usize) -> String {
+  *pos += 1; //consume 'package'
+  let name = tokens[*pos].clone();
+  *pos += 1;
+  *pos += 1; //consume ';'
+  name
+}
+
+//parses `import "other.proto";`, stripping the surrounding quotes from the literal
+fn parse_import(tokens: &[String], pos: &mut usize) -> String {
+  *pos += 1; //consume 'import'
+  let path = tokens[*pos].trim_matches('"').to_string();
+  *pos += 1;
+  *pos += 1; //consume ';'
+  path
+}
+
+/*
+The result of parsing a single .proto file: its own `message`/`enum` blocks (unqualified, as
+declared in the file), plus the `package` it declared itself in (if any) and the `import` paths it
+depends on. proto_loader.rs consumes this to resolve cross-file references into a single
+fully-qualified proto_map.
+ */
+#[derive(Default)]
+pub struct ParsedFile {
+  pub package: Option<String>,
+  pub imports: Vec<String>,
+  pub proto_map: HashMap<String, Proto>
+}
+
+/*
+Parses the full text of a single .proto file into a ParsedFile. Top-level `message` and `enum`
+blocks become entries of `proto_map`; `package` and `import` statements are captured so the loader
+can resolve names across files; `syntax` and any other top-level statement this parser doesn't
+model is skipped, one statement at a time.
+ */
+pub fn parse_proto_file(source: &str) -> ParsedFile {
+  let stripped = strip_comments(source);
+  let tokens = tokenize(&stripped);
+  let mut parsed = ParsedFile::default();
+
+  let mut pos = 0;
+  while pos < tokens.len() {
+    match tokens[pos].as_str() {
+      "message" => {
+        let (name, proto) = parse_message(&tokens, &mut pos);
+        parsed.proto_map.insert(name, proto);
+      }
+      "enum" => {
+        let (name, proto) = parse_enum(&tokens, &mut pos);
+        parsed.proto_map.insert(name, proto);
+      }
+      "package" => parsed.package = Some(parse_package(&tokens, &mut pos)),
+      "import" => parsed.imports.push(parse_import(&tokens, &mut pos)),
+      _ => skip_statement(&tokens, &mut pos)
+    }
+  }
+  parsed
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_message_skips_reserved_and_option_statements_instead_of_panicking() {
+    let parsed = parse_proto_file("message Foo { reserved 8, 9, 10; reserved \"old_name\"; option deprecated = true; optional int32 bar = 1; }");
+
+    let foo = &parsed.proto_map["Foo"];
+    assert_eq!(foo.attributes.len(), 1);
+    assert_eq!(foo.attributes[&1].attribute_name, "bar");
+  }
+
+  #[test]
+  fn parse_enum_skips_option_statements_instead_of_panicking() {
+    let parsed = parse_proto_file("enum DataType { option allow_alias = true; UNDEFINED = 0; FLOAT = 1; }");
+
+    let data_type = &parsed.proto_map["DataType"];
+    assert_eq!(data_type.enum_values.get(&0), Some(&"UNDEFINED".to_string()));
+    assert_eq!(data_type.enum_values.get(&1), Some(&"FLOAT".to_string()));
+  }
+}
@@ -11,8 +11,10 @@ accordingly to Protocol Buffers v2(proto2) documentation (https://protobuf.dev/p
   - Repeated: means that the attribute could be present [0..N] times
   - Required: means that the message struct cannot be considered well-formed if this attribute is not present;
              currently this annotation is no more used but is maintained for backward compatibility
-  - Map: means that a certain scalar value has been encoded as "packed" (this is done by default in proto3, while must be specified
-         in proto2). e.g. Map<string, i32> shows an i32 value which is packed as a string encoding (with a certain LEN).
+  - Map: marks an attribute declared as `map<K, V>` in the .proto file. A map field is encoded on the wire as a
+         repeated LEN-delimited "entry" message carrying a `key` (tag 1) and a `value` (tag 2); it is unrelated
+         to "packed" encoding (see read_proto::decoder), which instead concerns how *Repeated* scalar fields
+         are laid out back-to-back inside a single LEN payload.
  */
 #[repr(C)]
 #[derive(Default, Debug, PartialEq, Clone)]
@@ -40,26 +42,43 @@ impl FromStr for ProtoAnnotation {
   }
 }
 
+/*
+This structure contains the two halves of a `map<K, V> name = N;` field (ONNX uses this, e.g.
+`map<string, int64>`, in `MapProto`/metadata entries). A plain `ProtoAttribute::attribute_type` can
+only hold one scalar type, which is why a field needs this separate variant to carry both the key
+and the value type.
+ */
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapType {
+  pub key_type: String,
+  pub value_type: String
+}
+
 /*
 This structure contains an Attribute of a Message struct in a .proto file. (e.g. optional string name = 1;)
   - annotation: this annotation specifies a modifier for the attribute(i.e. optional). This is only present in proto2 version, while it could be omitted in proto3 version
   - attribute_name: the name of the attribute (i.e. name)
-  - attribute_type: the type of the attribute (i.e. string)
+  - attribute_type: the type of the attribute (i.e. string). For a `map<K, V>` field this is the name of the
+              synthesized entry message (see proto_parser::parse_map_field), and map_type carries K and V.
   - tag: this is the number which identifies the attribute (i.e. 1)
+  - map_type: only Some(..) when this attribute was declared as `map<K, V>`
  */
 #[repr(C)]
 #[derive(Default, Debug, Clone)]
 pub struct ProtoAttribute {
   pub annotation: ProtoAnnotation,
   pub attribute_name: String,
-  pub attribute_type: String
+  pub attribute_type: String,
+  pub map_type: Option<MapType>
 }
 impl ProtoAttribute {
   pub(crate) fn new() -> Self {
     Self {
       annotation: Default::default(),
       attribute_name: Default::default(),
-      attribute_type: Default::default()
+      attribute_type: Default::default(),
+      map_type: Default::default()
     }
   }
 }
@@ -125,22 +144,39 @@ Specifically, let's make an example:
   - attributes: this HashMap contains the list of attributes. Each attribute is represented by a ProtoAttribute. The HashMap allows to
               execute O(1) searches once having the Tag(i32) key to search.
   - contents: this HashMap allows to contain other "message"/"oneof" structures recursively, preserving the O(1) access time
+  - enum_values/enum_names: only populated when kind_of is Enum; they are the forward (number -> name) and
+              reverse (name -> number) maps of an `enum Foo { BAR = 0; BAZ = 2; }` block, left empty otherwise
 */
 #[repr(C)]
 #[derive(Default, Clone)]
 pub struct Proto {
   pub kind_of: KindOf, //one value between [Message, OneOf, Enum]
   pub attributes: HashMap<i32, ProtoAttribute>, //<tag, ProtoAttribute>
-  pub contents: HashMap<String, Proto> //<name, Proto>, since a message could contain itself others messages/one-of
+  pub contents: HashMap<String, Proto>, //<name, Proto>, since a message could contain itself others messages/one-of
+  pub enum_values: HashMap<i32, String>, //<number, name>, only used when kind_of == Enum
+  pub enum_names: HashMap<String, i32> //<name, number>, reverse of enum_values
 }
 impl Proto{
   pub(crate) fn new(kind_of: KindOf) -> Self {
     Self {
       kind_of,
       attributes: HashMap::new(),
-      contents: HashMap::new()
+      contents: HashMap::new(),
+      enum_values: HashMap::new(),
+      enum_names: HashMap::new()
     }
   }
+
+  //resolves an enum number to its declared value name; per proto3 open-enum semantics an unrecognized
+  //number is not an error, so callers should keep the raw i32 around rather than treating this as fatal
+  pub fn from_i32(&self, n: i32) -> Option<&str> {
+    self.enum_values.get(&n).map(|name| name.as_str())
+  }
+
+  //whether `n` is one of the declared value numbers of this enum
+  pub fn is_valid(&self, n: i32) -> bool {
+    self.enum_values.contains_key(&n)
+  }
 }
 impl Debug for Proto{
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -0,0 +1,196 @@
+use ndarray::{ArrayD, IxDyn};
+use crate::read_proto::decoder::{DynMessage, DynValue};
+
+/*
+ONNX delivers a tensor's contents most often as a single `bytes` field (`TensorProto.raw_data`)
+whose meaning depends on the sibling `data_type` enum, or alternatively as one of the typed
+`float_data`/`int64_data`/... fields when the producer chose not to pack raw bytes. This module
+reads both forms and reshapes the result into an ndarray according to `dims`.
+
+These are the `TensorProto.DataType` enum numbers this module currently interprets (see onnx.proto);
+they cover the f32/i32/i64/f64 element types of TensorData, not the full ONNX type list.
+ */
+const FLOAT: i32 = 1;
+const INT32: i32 = 6;
+const INT64: i32 = 7;
+const DOUBLE: i32 = 11;
+
+#[derive(Debug)]
+pub enum TensorData {
+  F32(ArrayD<f32>),
+  F64(ArrayD<f64>),
+  I32(ArrayD<i32>),
+  I64(ArrayD<i64>)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TensorError {
+  UnsupportedDataType(i32),
+  MissingDims,
+  InvalidShape,
+  ElementCountMismatch { expected: usize, actual: usize }
+}
+
+//reads a repeated field as a Vec regardless of whether the decoder folded it into DynValue::Repeated
+//or (the single-element case) left it as a bare scalar value, see DynMessage::insert
+fn repeated_field(message: &DynMessage, name: &str) -> Vec<DynValue> {
+  match message.fields.get(name) {
+    None => Vec::new(),
+    Some(DynValue::Repeated(values)) => values.clone(),
+    Some(value) => vec![value.clone()]
+  }
+}
+
+fn dims_of(message: &DynMessage) -> Result<Vec<usize>, TensorError> {
+  let dims = repeated_field(message, "dims");
+  if dims.is_empty() {
+    return Err(TensorError::MissingDims);
+  }
+  dims.into_iter()
+    .map(|d| match d {
+      DynValue::Varint(n) if n >= 0 => Ok(n as usize),
+      _ => Err(TensorError::InvalidShape)
+    })
+    .collect()
+}
+
+//reads `data_type` whether the schema resolved it to an enum (DynValue::Enum) or, absent that
+//schema information, a plain varint (DynValue::Varint); see DynMessage::enum_field
+fn data_type_of(message: &DynMessage) -> i32 {
+  match message.fields.get("data_type") {
+    Some(DynValue::Enum(n)) => *n,
+    Some(DynValue::Varint(n)) => *n as i32,
+    _ => 0
+  }
+}
+
+fn raw_data_of(message: &DynMessage) -> Option<&[u8]> {
+  match message.fields.get("raw_data") {
+    Some(DynValue::Bytes(bytes)) if !bytes.is_empty() => Some(bytes.as_slice()),
+    _ => None
+  }
+}
+
+fn le_chunks<T, const N: usize>(raw: &[u8], from_le_bytes: impl Fn([u8; N]) -> T) -> Vec<T> {
+  raw.chunks_exact(N).map(|chunk| from_le_bytes(chunk.try_into().unwrap())).collect()
+}
+
+//reshapes `values` into an ArrayD<T> of shape `dims`, failing if the element count doesn't match
+//the product of dims (as the request asks, rather than silently truncating or padding)
+fn reshape<T>(dims: Vec<usize>, values: Vec<T>) -> Result<ArrayD<T>, TensorError> {
+  let expected: usize = dims.iter().product();
+  if values.len() != expected {
+    return Err(TensorError::ElementCountMismatch { expected, actual: values.len() });
+  }
+  ArrayD::from_shape_vec(IxDyn(&dims), values).map_err(|_| TensorError::InvalidShape)
+}
+
+/*
+Reads `data_type` and `dims` off a decoded `TensorProto` message and reinterprets its contents
+(`raw_data` if populated, otherwise the matching typed `*_data` field) into an ndarray::ArrayD of
+the proper element type, reshaped to `dims`.
+ */
+pub fn tensor_from_proto(message: &DynMessage) -> Result<TensorData, TensorError> {
+  let dims = dims_of(message)?;
+  let raw_data = raw_data_of(message);
+
+  match data_type_of(message) {
+    FLOAT => {
+      let values = match raw_data {
+        Some(raw) => le_chunks(raw, f32::from_le_bytes),
+        None => repeated_field(message, "float_data")
+          .into_iter()
+          .map(|v| if let DynValue::Fixed32(f) = v { f } else { 0.0 })
+          .collect()
+      };
+      reshape(dims, values).map(TensorData::F32)
+    }
+    DOUBLE => {
+      let values = match raw_data {
+        Some(raw) => le_chunks(raw, f64::from_le_bytes),
+        None => repeated_field(message, "double_data")
+          .into_iter()
+          .map(|v| if let DynValue::Fixed64(f) = v { f } else { 0.0 })
+          .collect()
+      };
+      reshape(dims, values).map(TensorData::F64)
+    }
+    INT32 => {
+      let values = match raw_data {
+        Some(raw) => le_chunks(raw, i32::from_le_bytes),
+        None => repeated_field(message, "int32_data")
+          .into_iter()
+          .map(|v| if let DynValue::Varint(n) = v { n as i32 } else { 0 })
+          .collect()
+      };
+      reshape(dims, values).map(TensorData::I32)
+    }
+    INT64 => {
+      let values = match raw_data {
+        Some(raw) => le_chunks(raw, i64::from_le_bytes),
+        None => repeated_field(message, "int64_data")
+          .into_iter()
+          .map(|v| if let DynValue::Varint(n) = v { n } else { 0 })
+          .collect()
+      };
+      reshape(dims, values).map(TensorData::I64)
+    }
+    other => Err(TensorError::UnsupportedDataType(other))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn message_with(fields: Vec<(&str, DynValue)>) -> DynMessage {
+    let mut message = DynMessage::new();
+    for (name, value) in fields {
+      message.fields.insert(name.to_string(), value);
+    }
+    message
+  }
+
+  #[test]
+  fn reinterprets_raw_data_bytes_as_a_float_tensor() {
+    let mut raw = Vec::new();
+    for v in [1.0f32, 2.0, 3.0, 4.0] {
+      raw.extend(v.to_le_bytes());
+    }
+    let message = message_with(vec![
+      ("dims", DynValue::Repeated(vec![DynValue::Varint(2), DynValue::Varint(2)])),
+      ("data_type", DynValue::Varint(FLOAT as i64)),
+      ("raw_data", DynValue::Bytes(raw))
+    ]);
+
+    match tensor_from_proto(&message).unwrap() {
+      TensorData::F32(array) => assert_eq!(array.into_raw_vec(), vec![1.0, 2.0, 3.0, 4.0]),
+      other => panic!("expected an F32 tensor, got {:?}", other)
+    }
+  }
+
+  #[test]
+  fn falls_back_to_the_typed_field_when_raw_data_is_absent() {
+    let message = message_with(vec![
+      ("dims", DynValue::Varint(3)),
+      ("data_type", DynValue::Varint(INT64 as i64)),
+      ("int64_data", DynValue::Repeated(vec![DynValue::Varint(1), DynValue::Varint(2), DynValue::Varint(3)]))
+    ]);
+
+    match tensor_from_proto(&message).unwrap() {
+      TensorData::I64(array) => assert_eq!(array.into_raw_vec(), vec![1, 2, 3]),
+      other => panic!("expected an I64 tensor, got {:?}", other)
+    }
+  }
+
+  #[test]
+  fn rejects_an_element_count_that_does_not_match_dims_instead_of_truncating() {
+    let message = message_with(vec![
+      ("dims", DynValue::Repeated(vec![DynValue::Varint(2), DynValue::Varint(2)])),
+      ("data_type", DynValue::Varint(INT32 as i64)),
+      ("int32_data", DynValue::Repeated(vec![DynValue::Varint(1)]))
+    ]);
+
+    assert_eq!(tensor_from_proto(&message).unwrap_err(), TensorError::ElementCountMismatch { expected: 4, actual: 1 });
+  }
+}